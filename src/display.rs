@@ -30,6 +30,10 @@ impl DisplayManager {
         Display::new(&self.context)
     }
 
+    pub fn new_debug_viewer(&self) -> Result<DebugViewer> {
+        DebugViewer::new(&self.context)
+    }
+
     pub fn poll_event(&mut self) -> DisplayEvent {
         for event in self.event_pump.poll_iter() {
             match event {
@@ -64,16 +68,32 @@ pub struct Display {
     time: Instant,
     last_frame: Instant,
     limit_framerate: bool,
+    correct_colors: bool,
+    gamma_decode: [f64; 256],
+    gamma_encode: [u8; 256],
     canvas: Canvas<Window>,
 }
 
 impl Display {
     pub fn new(context: &sdl2::Sdl) -> Result<Self> {
+        // Precompute the two gamma curves used by the LCD colour-correction
+        // pass: decode each 8-bit channel to linear light, and re-encode the
+        // corrected mix with ~2.2 gamma. Cheap since the DMG viewport only
+        // ever contains four distinct source colours.
+        let mut gamma_decode = [0.0; 256];
+        let mut gamma_encode = [0u8; 256];
+        for i in 0..256 {
+            gamma_decode[i] = (i as f64 / 255.0).powf(2.2);
+            gamma_encode[i] = ((i as f64 / 255.0).powf(1.0 / 2.2) * 255.0).round() as u8;
+        }
         Ok(Display {
             frames: 0,
             time: Instant::now(),
             last_frame: Instant::now(),
             limit_framerate: true,
+            correct_colors: false,
+            gamma_decode,
+            gamma_encode,
             canvas: context
                 .video()
                 .map_err(Error::msg)?
@@ -93,6 +113,26 @@ impl Display {
         self.limit_framerate = !self.limit_framerate;
     }
 
+    pub fn toggle_color_correction(&mut self) {
+        self.correct_colors = !self.correct_colors;
+    }
+
+    /// Approximate the washed-out DMG/GBC LCD: linearize the channels, apply
+    /// cross-channel bleed, then re-encode with ~2.2 gamma.
+    fn correct(&self, color: Color) -> Color {
+        let (r, g, b) = (
+            self.gamma_decode[color.r as usize],
+            self.gamma_decode[color.g as usize],
+            self.gamma_decode[color.b as usize],
+        );
+        let mix = |x: f64| (x.clamp(0.0, 1.0) * 255.0).round() as usize;
+        Color::RGB(
+            self.gamma_encode[mix(0.80 * r + 0.10 * g + 0.10 * b)],
+            self.gamma_encode[mix(0.20 * r + 0.70 * g + 0.10 * b)],
+            self.gamma_encode[mix(0.15 * r + 0.15 * g + 0.70 * b)],
+        )
+    }
+
     pub fn draw(&mut self, pixels: [[Color; W_WIDTH]; W_HEIGHT]) {
         self.canvas.set_draw_color(Color::WHITE);
         self.canvas.clear();
@@ -101,6 +141,11 @@ impl Display {
                 match color {
                     Color::WHITE => continue,
                     _ => {
+                        let color = if self.correct_colors {
+                            self.correct(color)
+                        } else {
+                            color
+                        };
                         self.canvas.set_draw_color(color);
                         self.canvas
                             .fill_rect(Rect::new(
@@ -132,3 +177,141 @@ impl Display {
         }
     }
 }
+
+/// A single secondary debugging window rendering an arbitrary grid of colors.
+struct DebugWindow {
+    canvas: Canvas<Window>,
+    scale: u32,
+}
+
+impl DebugWindow {
+    fn new(
+        video: &sdl2::VideoSubsystem,
+        title: &str,
+        width: u32,
+        height: u32,
+        scale: u32,
+    ) -> Result<Self> {
+        Ok(DebugWindow {
+            canvas: video
+                .window(title, width * scale, height * scale)
+                .position_centered()
+                .build()?
+                .into_canvas()
+                .build()?,
+            scale,
+        })
+    }
+
+    /// Fill the window from a grid of colors, optionally outlining a rectangle
+    /// (in grid coordinates) to mark the scroll viewport on a tilemap.
+    fn draw(&mut self, pixels: &[Vec<Color>], outline: Option<(i32, i32, u32, u32)>) {
+        let s = self.scale;
+        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.clear();
+        for (i, row) in pixels.iter().enumerate() {
+            for (j, &color) in row.iter().enumerate() {
+                self.canvas.set_draw_color(color);
+                self.canvas
+                    .fill_rect(Rect::new(j as i32 * s as i32, i as i32 * s as i32, s, s))
+                    .unwrap();
+            }
+        }
+        if let Some((x, y, w, h)) = outline {
+            self.canvas.set_draw_color(Color::RGB(0xFF, 0x00, 0x00));
+            self.canvas
+                .draw_rect(Rect::new(x * s as i32, y * s as i32, w * s, h * s))
+                .unwrap();
+        }
+        self.canvas.present();
+    }
+}
+
+/// Optional secondary windows visualizing VRAM and OAM, toggled at runtime
+/// via hotkeys and refreshed by the PPU once per frame.
+pub struct DebugViewer {
+    video: sdl2::VideoSubsystem,
+    tiles: Option<DebugWindow>,
+    bg_map: Option<DebugWindow>,
+    win_map: Option<DebugWindow>,
+    oam: Option<DebugWindow>,
+}
+
+impl DebugViewer {
+    fn new(context: &sdl2::Sdl) -> Result<Self> {
+        Ok(DebugViewer {
+            video: context.video().map_err(Error::msg)?,
+            tiles: None,
+            bg_map: None,
+            win_map: None,
+            oam: None,
+        })
+    }
+
+    /// Open or close one of the debug windows in response to a hotkey.
+    pub fn toggle(&mut self, key: &str) -> Result<()> {
+        match key {
+            "T" => self.tiles = Self::flip(self.tiles.take(), &self.video, "VRAM Tiles", 256, 256)?,
+            "B" => {
+                self.bg_map = Self::flip(self.bg_map.take(), &self.video, "BG Tilemap", 256, 256)?
+            }
+            "N" => {
+                self.win_map =
+                    Self::flip(self.win_map.take(), &self.video, "Window Tilemap", 256, 256)?
+            }
+            "O" => self.oam = Self::flip(self.oam.take(), &self.video, "OAM", 64, 80)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn flip(
+        current: Option<DebugWindow>,
+        video: &sdl2::VideoSubsystem,
+        title: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<Option<DebugWindow>> {
+        match current {
+            Some(_) => Ok(None),
+            None => Ok(Some(DebugWindow::new(video, title, width, height, 2)?)),
+        }
+    }
+
+    pub fn tiles_visible(&self) -> bool {
+        self.tiles.is_some()
+    }
+    pub fn bg_map_visible(&self) -> bool {
+        self.bg_map.is_some()
+    }
+    pub fn win_map_visible(&self) -> bool {
+        self.win_map.is_some()
+    }
+    pub fn oam_visible(&self) -> bool {
+        self.oam.is_some()
+    }
+
+    pub fn draw_tiles(&mut self, pixels: &[Vec<Color>]) {
+        if let Some(w) = self.tiles.as_mut() {
+            w.draw(pixels, None);
+        }
+    }
+
+    pub fn draw_bg_map(&mut self, pixels: &[Vec<Color>], outline: (i32, i32, u32, u32)) {
+        if let Some(w) = self.bg_map.as_mut() {
+            w.draw(pixels, Some(outline));
+        }
+    }
+
+    pub fn draw_win_map(&mut self, pixels: &[Vec<Color>], outline: (i32, i32, u32, u32)) {
+        if let Some(w) = self.win_map.as_mut() {
+            w.draw(pixels, Some(outline));
+        }
+    }
+
+    pub fn draw_oam(&mut self, pixels: &[Vec<Color>]) {
+        if let Some(w) = self.oam.as_mut() {
+            w.draw(pixels, None);
+        }
+    }
+}
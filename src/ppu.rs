@@ -1,4 +1,7 @@
 #![allow(non_snake_case)]
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
 use anyhow::Result;
 use sdl2::pixels::Color;
 
@@ -36,34 +39,215 @@ impl PPURegisters {
     }
 }
 
+/// A scheduled PPU timing event, ordered by its absolute dot timestamp
+/// (see [`PPU::events`]). The variant's derived order only breaks ties
+/// between events due at the same dot.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PpuEvent {
+    /// Start of a scanline (mode 2 for visible lines, V-blank at line 144).
+    LineStart,
+    /// Start of mode 3; the pixel pipeline then runs until the line is drawn.
+    Mode3Start,
+}
+
+/// CGB-only video state: the selected VRAM bank (`VBK`) and the two
+/// 64-byte palette RAMs, each holding eight four-color RGB555 palettes
+/// addressed through an auto-incrementing index register.
+struct CgbRegisters {
+    vbk: usize,
+    bgpi: u8,
+    obpi: u8,
+    bg_palette: [u8; 64],
+    obj_palette: [u8; 64],
+}
+
+impl CgbRegisters {
+    fn new() -> Self {
+        CgbRegisters {
+            vbk: 0,
+            bgpi: 0,
+            obpi: 0,
+            bg_palette: [0; 64],
+            obj_palette: [0; 64],
+        }
+    }
+}
+
+/// An in-progress OAM DMA transfer started by a write to `0xFF46`.
+///
+/// The 160-byte copy takes ~160 machine cycles, so one byte is consumed
+/// every 4 clocks from `draw`. While it is active the CPU cannot see OAM.
+#[derive(Default)]
+struct OamDma {
+    base: u8,
+    remaining: u8,
+}
+
+impl OamDma {
+    fn active(&self) -> bool {
+        self.remaining > 0
+    }
+}
+
+/// The background fetcher walks these four steps: the first three take 2
+/// dots each, and `Push` pushes 8 pixels into the background FIFO on the
+/// first dot the FIFO is empty.
+#[derive(Clone, Copy, PartialEq)]
+enum FetchState {
+    GetTile,
+    GetLow,
+    GetHigh,
+    Push,
+}
+
+/// State of the mode-3 pixel pipeline for the scanline being drawn.
+///
+/// Drawing is no longer instantaneous: the fetcher and the two FIFOs are
+/// stepped one dot at a time, so mode 3 lasts as long as the fetcher takes
+/// to shift 160 pixels onto the line (plus fine-scroll discards and window
+/// restarts), which is what makes mid-scanline register writes observable.
+/// Sprites are mixed in without stalling the fetcher, so they do not
+/// lengthen mode 3.
+struct Fetcher {
+    state: FetchState,
+    sub_dot: u8,
+    tile_x: u8,
+    tile_num: u8,
+    attr: u8,
+    lo: u8,
+    hi: u8,
+    window: bool,
+    bg_fifo: VecDeque<u8>,
+    bg_attr_fifo: VecDeque<u8>,
+    obj_fifo: VecDeque<ObjPixel>,
+    lx: u8,
+    discard: u8,
+    sprites: Vec<OamEntry>,
+    finished: bool,
+}
+
+#[derive(Clone, Copy)]
+struct ObjPixel {
+    color: u8,
+    obp1: bool,
+    priority: bool,
+    /// CGB OBJ palette number (flags bits 0-2); ignored in DMG mode.
+    cgb_pal: u8,
+}
+
+#[derive(Clone, Copy)]
+struct OamEntry {
+    y: u8,
+    x: u8,
+    tile: u8,
+    flags: u8,
+    drawn: bool,
+}
+
+impl Fetcher {
+    fn new() -> Self {
+        Fetcher {
+            state: FetchState::GetTile,
+            sub_dot: 0,
+            tile_x: 0,
+            tile_num: 0,
+            attr: 0,
+            lo: 0,
+            hi: 0,
+            window: false,
+            bg_fifo: VecDeque::new(),
+            bg_attr_fifo: VecDeque::new(),
+            obj_fifo: VecDeque::new(),
+            lx: 0,
+            discard: 0,
+            sprites: Vec::new(),
+            finished: true,
+        }
+    }
+}
+
 pub struct PPU {
-    pub memory: [u8; 0x2000],
+    pub memory: [[u8; 0x2000]; 2],
     pub oam: [u8; 0xA0],
     viewport: [[Color; W_WIDTH]; W_HEIGHT],
     registers: PPURegisters,
     display: Display,
     enable_display_events: bool,
     block_stat_irqs: bool,
+    dma: OamDma,
+    fetcher: Fetcher,
+    cgb: bool,
+    cgb_regs: CgbRegisters,
+    dmg_palette: [Color; 4],
+    debug: DebugViewer,
+    events: BinaryHeap<Reverse<(u64, PpuEvent)>>,
     cycles: u64,
 }
 
+/// The default four DMG shades, darkest colour last, used unless overridden
+/// through [`PPU::set_palette`].
+const GRAYSCALE_PALETTE: [Color; 4] = [
+    Color::WHITE,
+    Color::RGB(0xAA, 0xAA, 0xAA),
+    Color::RGB(0x55, 0x55, 0x55),
+    Color::BLACK,
+];
+
 impl PPU {
     pub fn new() -> Result<Self> {
+        Self::with_mode(false)
+    }
+
+    /// Construct a PPU in CGB mode, enabling VRAM/palette banking and the
+    /// RGB555 palettes. DMG games keep using `new` and the `BGP`/`OBP`
+    /// registers.
+    pub fn new_cgb() -> Result<Self> {
+        Self::with_mode(true)
+    }
+
+    fn with_mode(cgb: bool) -> Result<Self> {
         Ok(PPU {
-            memory: [0; 0x2000],
+            memory: [[0; 0x2000]; 2],
             oam: [0; 0xA0],
             viewport: [[Color::WHITE; W_WIDTH]; W_HEIGHT],
             registers: PPURegisters::new(),
             display: Display::new()?,
             enable_display_events: false,
             block_stat_irqs: false,
+            dma: OamDma::default(),
+            fetcher: Fetcher::new(),
+            cgb,
+            cgb_regs: CgbRegisters::new(),
+            dmg_palette: GRAYSCALE_PALETTE,
+            debug: DisplayManager::new()?.new_debug_viewer()?,
+            events: {
+                // Prime the queue with the first scanline; every handler
+                // enqueues its successor from then on.
+                let mut events = BinaryHeap::new();
+                events.push(Reverse((0, PpuEvent::LineStart)));
+                events
+            },
             cycles: 0,
         })
     }
 
+    /// Toggle one of the VRAM/OAM debug windows, wired to a hotkey by the
+    /// caller through [`DisplayEvent::KeyEvent`].
+    pub fn toggle_debug_window(&mut self, key: &str) -> Result<()> {
+        self.debug.toggle(key)
+    }
+
+    /// Override the four DMG shades (lightest to darkest), e.g. the classic
+    /// green LCD set, so the output can match original-hardware tints.
+    pub fn set_palette(&mut self, palette: [Color; 4]) {
+        self.dmg_palette = palette;
+    }
+
     pub fn read_byte(&self, addr: u16) -> u8 {
         match addr {
-            0x8000..=0x9FFF => self.memory[addr as usize - 0x8000],
+            0x8000..=0x9FFF => self.memory[self.cgb_regs.vbk][addr as usize - 0x8000],
+            // OAM is inaccessible to the CPU while a DMA transfer is running.
+            0xFE00..=0xFE9F if self.dma.active() => 0xFF,
             0xFE00..=0xFE9F => self.oam[addr as usize - 0xFE00],
             0xFF40 => self.registers.LCDC,
             0xFF41 => self.registers.STAT,
@@ -76,24 +260,46 @@ impl PPU {
             0xFF49 => self.registers.OBP1,
             0xFF4A => self.registers.WY,
             0xFF4B => self.registers.WX,
+            0xFF4F if self.cgb => 0xFE | self.cgb_regs.vbk as u8,
+            0xFF68 if self.cgb => self.cgb_regs.bgpi,
+            0xFF69 if self.cgb => self.cgb_regs.bg_palette[(self.cgb_regs.bgpi & 0x3F) as usize],
+            0xFF6A if self.cgb => self.cgb_regs.obpi,
+            0xFF6B if self.cgb => self.cgb_regs.obj_palette[(self.cgb_regs.obpi & 0x3F) as usize],
             _ => panic!("Invalid PPU Register read: {:04x}", addr),
         }
     }
 
     pub fn write_byte(&mut self, addr: u16, val: u8) {
         match addr {
-            0x8000..=0x9FFF => self.memory[addr as usize - 0x8000] = val,
+            0x8000..=0x9FFF => self.memory[self.cgb_regs.vbk][addr as usize - 0x8000] = val,
+            0xFE00..=0xFE9F if self.dma.active() => {}
             0xFE00..=0xFE9F => self.oam[addr as usize - 0xFE00] = val,
             0xFF40 => self.registers.LCDC = val,
             0xFF41 => self.registers.STAT |= val & !7,
             0xFF42 => self.registers.SCY = val,
             0xFF43 => self.registers.SCX = val,
             0xFF45 => self.registers.LYC = val,
+            0xFF46 => self.dma = OamDma { base: val, remaining: 0xA0 },
             0xFF47 => self.registers.BGP = val,
             0xFF48 => self.registers.OBP0 = val,
             0xFF49 => self.registers.OBP1 = val,
             0xFF4A => self.registers.WY = val,
             0xFF4B => self.registers.WX = val,
+            0xFF4F if self.cgb => self.cgb_regs.vbk = (val & 1) as usize,
+            0xFF68 if self.cgb => self.cgb_regs.bgpi = val,
+            0xFF69 if self.cgb => {
+                self.cgb_regs.bg_palette[(self.cgb_regs.bgpi & 0x3F) as usize] = val;
+                if self.cgb_regs.bgpi & 0x80 != 0 {
+                    self.cgb_regs.bgpi = 0x80 | ((self.cgb_regs.bgpi + 1) & 0x3F);
+                }
+            }
+            0xFF6A if self.cgb => self.cgb_regs.obpi = val,
+            0xFF6B if self.cgb => {
+                self.cgb_regs.obj_palette[(self.cgb_regs.obpi & 0x3F) as usize] = val;
+                if self.cgb_regs.obpi & 0x80 != 0 {
+                    self.cgb_regs.obpi = 0x80 | ((self.cgb_regs.obpi + 1) & 0x3F);
+                }
+            }
             _ => panic!("Invalid PPU Register write: {:04x}", addr),
         }
     }
@@ -117,56 +323,106 @@ impl PPU {
         res
     }
 
-    pub fn draw(&mut self, cycles_passed: u64) {
+    pub fn draw<F: FnMut(u16) -> u8>(&mut self, cycles_passed: u64, mut read_src: F) {
         for _ in 0..cycles_passed / 4 {
-            self.cycles += 4;
-            if self.cycles > 70224 {
-                self.cycles -= 70224;
-                self.enable_display_events = true;
-                self.display.draw(self.viewport);
-                // self.display.draw(self.dump_tiles(0x8000));
+            self.step_dma(&mut read_src);
+        }
+
+        // Advance the dot clock by `cycles_passed`, firing every scheduled
+        // event that has elapsed. The only place the clock is examined per
+        // dot is mode 3, where the pixel pipeline must tick; elsewhere we
+        // jump straight to the next event, so no 456-modulo runs in the loop.
+        let mut remaining = cycles_passed;
+        while remaining > 0 {
+            self.fire_due_events();
+
+            if self.registers.STAT & 0b11 == 3 && !self.fetcher.finished {
+                self.tick_fetcher();
+                if self.fetcher.finished {
+                    self.set_mode(0); // H-blank: mode-3 length is variable
+                }
+                self.advance(1, &mut remaining);
+            } else {
+                let next_event = self
+                    .events
+                    .peek()
+                    .map_or(u64::MAX, |Reverse((t, _))| t - self.cycles);
+                let step = next_event.min(70224 - self.cycles).min(remaining).max(1);
+                self.advance(step, &mut remaining);
             }
-            self.step();
         }
+        self.fire_due_events();
     }
 
-    fn step(&mut self) {
-        let clocks = self.cycles % 456;
-        let scanline = (self.cycles / 456) as u8;
+    /// Advance the clock by `step` dots, handling the end-of-frame wrap.
+    fn advance(&mut self, step: u64, remaining: &mut u64) {
+        self.cycles += step;
+        *remaining -= step;
+        if self.cycles >= 70224 {
+            self.cycles -= 70224;
+            self.enable_display_events = true;
+            self.display.draw(self.viewport);
+            self.refresh_debug();
+            self.events.push(Reverse((0, PpuEvent::LineStart)));
+        }
+    }
 
-        // Start of a line
-        if scanline != self.registers.LY {
-            self.block_stat_irqs = false;
-            if scanline == 0 {
-                self.registers.WC = 0;
+    fn fire_due_events(&mut self) {
+        while let Some(Reverse((t, _))) = self.events.peek() {
+            if *t > self.cycles {
+                break;
             }
+            let Reverse((_, event)) = self.events.pop().unwrap();
+            self.handle_event(event);
         }
-        self.registers.LY = scanline;
+    }
 
-        // Check for LY = LYC
-        let coincidence = self.registers.LY == self.registers.LYC;
-        if coincidence && clocks == 0 {
-            self.req_stat_interrupt(6);
-        }
-        self.registers.STAT &= !(1 << 2);
-        self.registers.STAT |= (coincidence as u8) << 2;
+    fn handle_event(&mut self, event: PpuEvent) {
+        match event {
+            PpuEvent::LineStart => {
+                let line = (self.cycles / 456) as u8;
+                self.block_stat_irqs = false;
+                if line == 0 {
+                    self.registers.WC = 0;
+                }
+                self.registers.LY = line;
 
-        // PPU Mode switching
-        if self.registers.LY < 144 {
-            match clocks {
-                0 => self.set_mode(2), // OAM Search
-                80 => {
-                    self.set_mode(3); // Drawing
-                    self.draw_line();
+                // Check for LY = LYC
+                let coincidence = self.registers.LY == self.registers.LYC;
+                if coincidence {
+                    self.req_stat_interrupt(6);
                 }
-                252 => self.set_mode(0), // H-blank
-                _ => {}
+                self.registers.STAT &= !(1 << 2);
+                self.registers.STAT |= (coincidence as u8) << 2;
+
+                if line < 144 {
+                    self.set_mode(2); // OAM Search
+                    self.events
+                        .push(Reverse((self.cycles + 80, PpuEvent::Mode3Start)));
+                } else if line == 144 {
+                    self.registers.interrupts.vblank = true;
+                    self.set_mode(1); // V-blank
+                }
+                // Line 153 is followed by the frame wrap, which re-primes the
+                // queue, so only earlier lines schedule their successor here.
+                if line < 153 {
+                    self.events
+                        .push(Reverse((self.cycles + 456, PpuEvent::LineStart)));
+                }
+            }
+            PpuEvent::Mode3Start => {
+                self.set_mode(3); // Drawing
+                self.start_line();
             }
         }
-        // V-blank
-        else if self.registers.LY == 144 && clocks == 0 {
-            self.registers.interrupts.vblank = true;
-            self.set_mode(1);
+    }
+
+    fn step_dma<F: FnMut(u16) -> u8>(&mut self, read_src: &mut F) {
+        if self.dma.active() {
+            let i = 0xA0 - self.dma.remaining as u16;
+            let src = (self.dma.base as u16) << 8 | i;
+            self.oam[i as usize] = read_src(src);
+            self.dma.remaining -= 1;
         }
     }
 
@@ -188,84 +444,376 @@ impl PPU {
         }
     }
 
-    fn draw_line(&mut self) {
-        // LCD Enable
-        if self.registers.LCDC & (1 << 7) != 0 {
-            // bg/win enable
-            if self.registers.LCDC & 1 != 0 {
-                let bg_tilemap = match self.registers.LCDC & (1 << 3) != 0 {
-                    true => 0x9C00,
-                    false => 0x9800,
-                };
-                let bg_y = self.registers.SCY.wrapping_add(self.registers.LY);
-                for i in 0u8..32 {
-                    let bg_tile_num =
-                        self.read_byte(bg_tilemap + 32 * ((bg_y / 8) as u16) + i as u16);
-                    let bg_tile_row = self.decode_tile_row(bg_tile_num, bg_y % 8);
-                    for j in 0u8..8 {
-                        let bg_x = (8 * i + j).wrapping_sub(self.registers.SCX) as usize;
-                        if bg_x < W_WIDTH {
-                            self.viewport[self.registers.LY as usize][bg_x] =
-                                self.decode_palette(bg_tile_row[j as usize]);
-                        }
+    /// Reset the pixel pipeline for the scanline about to be drawn (mode 3).
+    fn start_line(&mut self) {
+        let f = &mut self.fetcher;
+        f.state = FetchState::GetTile;
+        f.sub_dot = 0;
+        f.tile_x = 0;
+        f.window = false;
+        f.bg_fifo.clear();
+        f.bg_attr_fifo.clear();
+        f.obj_fifo.clear();
+        f.lx = 0;
+        // Fine horizontal scroll: the first SCX % 8 background pixels are
+        // fetched but discarded before the line reaches the viewport.
+        f.discard = self.registers.SCX % 8;
+        f.sprites.clear();
+        f.finished = self.registers.LCDC & (1 << 7) == 0;
+
+        // OAM scan: collect up to 10 sprites intersecting this line in OAM
+        // order (ties between equal X are later resolved by index).
+        if self.registers.LCDC & (1 << 1) != 0 {
+            let height: u8 = if self.registers.LCDC & (1 << 2) != 0 { 16 } else { 8 };
+            for i in 0..40 {
+                let entry = &self.oam[i * 4..i * 4 + 4];
+                let y = entry[0].wrapping_sub(16);
+                if self.registers.LY.wrapping_sub(y) < height {
+                    self.fetcher.sprites.push(OamEntry {
+                        y: entry[0],
+                        x: entry[1],
+                        tile: entry[2],
+                        flags: entry[3],
+                        drawn: false,
+                    });
+                    if self.fetcher.sprites.len() == 10 {
+                        break;
                     }
                 }
             }
-            if self.registers.LCDC & (1 << 5) != 0 && self.registers.LY >= self.registers.WY {
-                let win_tilemap = match self.registers.LCDC & (1 << 6) != 0 {
-                    true => 0x9C00,
-                    false => 0x9800,
+            // Stable sort: lower X first, equal X keeps OAM order.
+            self.fetcher.sprites.sort_by_key(|s| s.x);
+        }
+    }
+
+    /// Advance the pixel pipeline by a single dot: step the background
+    /// fetcher, then shift at most one pixel onto the viewport.
+    fn tick_fetcher(&mut self) {
+        if self.fetcher.finished {
+            return;
+        }
+        self.run_fetcher_step();
+
+        // The LCD can only shift a pixel while the background FIFO has data.
+        if self.fetcher.bg_fifo.is_empty() {
+            return;
+        }
+
+        // Window activation: once WX is reached, the fetcher restarts in the
+        // window tilemap and the background FIFO is thrown away.
+        if !self.fetcher.window
+            && self.registers.LCDC & (1 << 5) != 0
+            && self.registers.LY >= self.registers.WY
+            && self.fetcher.lx + 7 >= self.registers.WX
+        {
+            self.fetcher.window = true;
+            self.fetcher.tile_x = 0;
+            self.fetcher.state = FetchState::GetTile;
+            self.fetcher.sub_dot = 0;
+            self.fetcher.bg_fifo.clear();
+            self.fetcher.bg_attr_fifo.clear();
+            return;
+        }
+
+        if self.fetcher.discard > 0 {
+            self.fetcher.bg_fifo.pop_front();
+            self.fetcher.bg_attr_fifo.pop_front();
+            self.fetcher.discard -= 1;
+            return;
+        }
+
+        self.fetch_sprites_at(self.fetcher.lx);
+        let bg = self.fetcher.bg_fifo.pop_front().unwrap();
+        let bg_attr = self.fetcher.bg_attr_fifo.pop_front().unwrap_or(0);
+        let obj = self.fetcher.obj_fifo.pop_front();
+        let mut color = if self.cgb {
+            self.decode_cgb_palette(&self.cgb_regs.bg_palette, bg_attr & 0x07, bg)
+        } else {
+            self.decode_palette(bg)
+        };
+        if let Some(o) = obj {
+            // In CGB mode the BG attribute's own priority bit also keeps a
+            // non-zero background pixel in front of the sprite.
+            let bg_over_obj = (o.priority || (self.cgb && bg_attr & (1 << 7) != 0)) && bg != 0;
+            if o.color != 0 && !bg_over_obj {
+                color = if self.cgb {
+                    self.decode_cgb_palette(&self.cgb_regs.obj_palette, o.cgb_pal, o.color)
+                } else {
+                    self.decode_obj_palette(o.color, o.obp1)
                 };
-                let mut window_visible = false;
-                for i in 0u8..32 {
-                    let win_tile_num = self
-                        .read_byte(win_tilemap + 32 * ((self.registers.WC / 8) as u16) + i as u16);
-                    let win_tile_row = self.decode_tile_row(win_tile_num, self.registers.WC % 8);
-                    for j in 0..8 {
-                        let win_x = 8 * i as usize + j + self.registers.WX as usize;
-                        if win_x >= 7 && win_x < W_WIDTH {
-                            window_visible = true;
-                            self.viewport[self.registers.LY as usize][win_x - 7] =
-                                self.decode_palette(win_tile_row[j]);
-                        }
+            }
+        }
+        self.viewport[self.registers.LY as usize][self.fetcher.lx as usize] = color;
+        self.fetcher.lx += 1;
+        if self.fetcher.lx as usize >= W_WIDTH {
+            self.fetcher.finished = true;
+            if self.fetcher.window {
+                self.registers.WC += 1;
+            }
+        }
+    }
+
+    /// Run the current fetcher state; the first three states each take two
+    /// dots, while `Push` waits until the FIFO has room for another tile.
+    fn run_fetcher_step(&mut self) {
+        match self.fetcher.state {
+            FetchState::GetTile => {
+                if self.tick_two_dots() {
+                    let (tile_num, attr) = self.fetch_bg_tile();
+                    self.fetcher.tile_num = tile_num;
+                    self.fetcher.attr = attr;
+                    self.fetcher.state = FetchState::GetLow;
+                }
+            }
+            FetchState::GetLow => {
+                if self.tick_two_dots() {
+                    let (bank, addr) = self.fetch_bg_tile_addr();
+                    self.fetcher.lo = self.read_vram(bank, addr);
+                    self.fetcher.state = FetchState::GetHigh;
+                }
+            }
+            FetchState::GetHigh => {
+                if self.tick_two_dots() {
+                    let (bank, addr) = self.fetch_bg_tile_addr();
+                    self.fetcher.hi = self.read_vram(bank, addr + 1);
+                    self.fetcher.state = FetchState::Push;
+                }
+            }
+            FetchState::Push => {
+                if self.fetcher.bg_fifo.is_empty() {
+                    let bg_enable = self.registers.LCDC & 1 != 0;
+                    // CGB tiles can be horizontally flipped via their attribute.
+                    let x_flip = self.cgb && self.fetcher.attr & (1 << 5) != 0;
+                    let attr = self.fetcher.attr;
+                    for i in 0..8 {
+                        let bit = if x_flip { i } else { 7 - i };
+                        let color = if bg_enable {
+                            (((self.fetcher.hi >> bit) & 1) << 1) | ((self.fetcher.lo >> bit) & 1)
+                        } else {
+                            0
+                        };
+                        self.fetcher.bg_fifo.push_back(color);
+                        self.fetcher.bg_attr_fifo.push_back(attr);
                     }
+                    self.fetcher.tile_x += 1;
+                    self.fetcher.state = FetchState::GetTile;
+                }
+            }
+        }
+    }
+
+    fn tick_two_dots(&mut self) -> bool {
+        self.fetcher.sub_dot += 1;
+        if self.fetcher.sub_dot >= 2 {
+            self.fetcher.sub_dot = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn read_vram(&self, bank: usize, addr: u16) -> u8 {
+        self.memory[bank][addr as usize - 0x8000]
+    }
+
+    /// Fetch the current tile number from the tilemap (bank 0) along with its
+    /// CGB attribute byte from bank 1 (always 0 in DMG mode).
+    fn fetch_bg_tile(&self) -> (u8, u8) {
+        let tilemap_addr = if self.fetcher.window {
+            let tilemap = if self.registers.LCDC & (1 << 6) != 0 { 0x9C00 } else { 0x9800 };
+            tilemap + 32 * (self.registers.WC / 8) as u16 + self.fetcher.tile_x as u16
+        } else {
+            let tilemap = if self.registers.LCDC & (1 << 3) != 0 { 0x9C00 } else { 0x9800 };
+            let bg_y = self.registers.SCY.wrapping_add(self.registers.LY);
+            let col = (self.registers.SCX / 8).wrapping_add(self.fetcher.tile_x) & 31;
+            tilemap + 32 * ((bg_y / 8) as u16) + col as u16
+        };
+        let tile_num = self.read_vram(0, tilemap_addr);
+        let attr = if self.cgb { self.read_vram(1, tilemap_addr) } else { 0 };
+        (tile_num, attr)
+    }
+
+    /// Resolve the VRAM bank and address for the current tile's pixel row,
+    /// honoring the CGB attribute's tile-bank and Y-flip bits.
+    fn fetch_bg_tile_addr(&self) -> (usize, u16) {
+        let mut row_num = if self.fetcher.window {
+            self.registers.WC % 8
+        } else {
+            self.registers.SCY.wrapping_add(self.registers.LY) % 8
+        };
+        let bank = if self.cgb && self.fetcher.attr & (1 << 3) != 0 { 1 } else { 0 };
+        if self.cgb && self.fetcher.attr & (1 << 6) != 0 {
+            row_num = 7 - row_num;
+        }
+        let tile_num = self.fetcher.tile_num;
+        let base = if self.registers.LCDC & (1 << 4) == 0 && tile_num <= 0x80 {
+            0x9000
+        } else {
+            0x8000
+        } + tile_num as u16 * 16
+            + 2 * row_num as u16;
+        (bank, base)
+    }
+
+    /// Overlay any collected sprites whose left edge falls on the current
+    /// pixel into the sprite FIFO, keeping already-present opaque pixels so
+    /// lower-X (and lower-index) sprites win on overlap.
+    fn fetch_sprites_at(&mut self, lx: u8) {
+        let height: u8 = if self.registers.LCDC & (1 << 2) != 0 { 16 } else { 8 };
+        for i in 0..self.fetcher.sprites.len() {
+            let sprite = self.fetcher.sprites[i];
+            if sprite.drawn {
+                continue;
+            }
+            let start = sprite.x as i16 - 8;
+            // A sprite is injected when the line reaches its left edge; those
+            // clipped off the left (X < 8) appear starting at pixel 0.
+            let skip = if start < 0 {
+                if lx != 0 {
+                    continue;
                 }
-                if window_visible {
-                    self.registers.WC += 1
+                (-start) as u8
+            } else {
+                if start != lx as i16 {
+                    continue;
                 }
+                0
+            };
+            self.fetcher.sprites[i].drawn = true;
+
+            let y_flip = sprite.flags & (1 << 6) != 0;
+            let x_flip = sprite.flags & (1 << 5) != 0;
+            let obp1 = sprite.flags & (1 << 4) != 0;
+            let priority = sprite.flags & (1 << 7) != 0;
+            let cgb_pal = sprite.flags & 0x07;
+            let bank = if self.cgb && sprite.flags & (1 << 3) != 0 { 1 } else { 0 };
+
+            let mut row = self.registers.LY.wrapping_sub(sprite.y.wrapping_sub(16));
+            if y_flip {
+                row = height - 1 - row;
             }
+            // In 8x16 mode the low bit of the tile index is forced to 0.
+            let tile_num = if height == 16 { sprite.tile & !1 } else { sprite.tile };
+            let tile_row = self.decode_obj_tile_row(bank, tile_num, row);
+
+            for j in skip..8 {
+                let color = tile_row[if x_flip { 7 - j } else { j } as usize];
+                let pos = (j - skip) as usize;
+                let pixel = ObjPixel { color, obp1, priority, cgb_pal };
+                match self.fetcher.obj_fifo.get_mut(pos) {
+                    // Keep an already-present opaque sprite pixel.
+                    Some(existing) if existing.color != 0 => {}
+                    Some(existing) => *existing = pixel,
+                    None => self.fetcher.obj_fifo.push_back(pixel),
+                }
+            }
+        }
+    }
+
+    /// Refresh whichever debug windows are currently open, once per frame.
+    fn refresh_debug(&mut self) {
+        if self.debug.tiles_visible() {
+            let buf = self.tile_data_buffer();
+            self.debug.draw_tiles(&buf);
+        }
+        if self.debug.bg_map_visible() {
+            let map = if self.registers.LCDC & (1 << 3) != 0 { 0x9C00 } else { 0x9800 };
+            let buf = self.tilemap_buffer(map);
+            let outline = (self.registers.SCX as i32, self.registers.SCY as i32, 160, 144);
+            self.debug.draw_bg_map(&buf, outline);
+        }
+        if self.debug.win_map_visible() {
+            let map = if self.registers.LCDC & (1 << 6) != 0 { 0x9C00 } else { 0x9800 };
+            let buf = self.tilemap_buffer(map);
+            let outline = (self.registers.WX as i32 - 7, self.registers.WY as i32, 160, 144);
+            self.debug.draw_win_map(&buf, outline);
+        }
+        if self.debug.oam_visible() {
+            let buf = self.oam_buffer();
+            self.debug.draw_oam(&buf);
         }
-        // TODO: Sprite rendering
     }
 
-    fn dump_tiles(&self, base: u16) -> [[Color; 256]; 256] {
-        let mut bg = [[Color::WHITE; 256]; 256];
-        for i in 0..256 {
-            let tile_addr = base + i * 16;
-            let tile_y = i / 32;
-            let tile_x = i % 32;
-            for j in 0..8 {
-                let hi = self.read_byte(tile_addr + 2 * j + 1);
-                let lo = self.read_byte(tile_addr + 2 * j);
+    /// Render the 384 tiles at `0x8000`-`0x97FF` (both the `0x8000` and
+    /// `0x9000` blocks) into a 256x256 color grid, 32 tiles per row.
+    fn tile_data_buffer(&self) -> Vec<Vec<Color>> {
+        let mut buf = vec![vec![Color::WHITE; 256]; 256];
+        for i in 0..384u16 {
+            let tile_addr = 0x8000 + i * 16;
+            let tile_y = (i / 32) as usize * 8;
+            let tile_x = (i % 32) as usize * 8;
+            for j in 0..8u16 {
+                let hi = self.read_vram(0, tile_addr + 2 * j + 1);
+                let lo = self.read_vram(0, tile_addr + 2 * j);
                 for k in 0..8 {
-                    bg[(8 * tile_y + j) as usize][(8 * tile_x + 7 - k) as usize] =
+                    buf[tile_y + j as usize][tile_x + 7 - k] =
                         self.decode_palette((((hi >> k) & 1) << 1) | ((lo >> k) & 1));
                 }
             }
         }
-        bg
+        buf
+    }
+
+    /// Render a full 256x256 background tilemap starting at `map_base`.
+    fn tilemap_buffer(&self, map_base: u16) -> Vec<Vec<Color>> {
+        let mut buf = vec![vec![Color::WHITE; 256]; 256];
+        for ty in 0..32u16 {
+            for tx in 0..32u16 {
+                let tile_num = self.read_vram(0, map_base + 32 * ty + tx);
+                let base = if self.registers.LCDC & (1 << 4) == 0 && tile_num <= 0x80 {
+                    0x9000
+                } else {
+                    0x8000
+                } + tile_num as u16 * 16;
+                for j in 0..8u16 {
+                    let hi = self.read_vram(0, base + 2 * j + 1);
+                    let lo = self.read_vram(0, base + 2 * j);
+                    for k in 0..8 {
+                        buf[(8 * ty + j) as usize][(8 * tx) as usize + 7 - k] =
+                            self.decode_palette((((hi >> k) & 1) << 1) | ((lo >> k) & 1));
+                    }
+                }
+            }
+        }
+        buf
     }
 
-    fn decode_tile_row(&self, tile_num: u8, row_num: u8) -> [u8; 8] {
+    /// Render the 40 OAM sprites into a grid, honoring the current sprite
+    /// height, flips and palette so attributes are visible at a glance.
+    fn oam_buffer(&self) -> Vec<Vec<Color>> {
+        let height: u16 = if self.registers.LCDC & (1 << 2) != 0 { 16 } else { 8 };
+        let mut buf = vec![vec![Color::BLACK; 64]; 80];
+        for i in 0..40usize {
+            let entry = &self.oam[i * 4..i * 4 + 4];
+            let tile = entry[2];
+            let flags = entry[3];
+            let obp1 = flags & (1 << 4) != 0;
+            let x_flip = flags & (1 << 5) != 0;
+            let y_flip = flags & (1 << 6) != 0;
+            let tile_num = if height == 16 { tile & !1 } else { tile };
+            let cell_x = (i % 8) * 8;
+            let cell_y = (i / 8) * 16;
+            for row in 0..height {
+                let r = if y_flip { height - 1 - row } else { row };
+                let tile_row = self.decode_obj_tile_row(0, tile_num, r as u8);
+                for j in 0..8 {
+                    let color = tile_row[if x_flip { 7 - j } else { j }];
+                    if color != 0 {
+                        buf[cell_y + row as usize][cell_x + j] = self.decode_obj_palette(color, obp1);
+                    }
+                }
+            }
+        }
+        buf
+    }
+
+    fn decode_obj_tile_row(&self, bank: usize, tile_num: u8, row_num: u8) -> [u8; 8] {
         let mut row = [0; 8];
-        let tile_row_offset = if self.registers.LCDC & (1 << 4) == 0 && tile_num <= 0x80 {
-            0x9000
-        } else {
-            0x8000
-        } + tile_num as u16 * 16
-            + 2 * row_num as u16;
-        let hi = self.read_byte(tile_row_offset + 1);
-        let lo = self.read_byte(tile_row_offset);
+        // Sprites always use the unsigned 0x8000 tile block; in 8x16 mode a
+        // row_num >= 8 spills naturally into the next (contiguous) tile.
+        let tile_row_offset = 0x8000 + tile_num as u16 * 16 + 2 * row_num as u16;
+        let hi = self.read_vram(bank, tile_row_offset + 1);
+        let lo = self.read_vram(bank, tile_row_offset);
         for i in 0..8 {
             row[7 - i] = (((hi >> i) & 1) << 1) | ((lo >> i) & 1);
         }
@@ -274,12 +822,30 @@ impl PPU {
 
     fn decode_palette(&self, color: u8) -> Color {
         let color = (self.registers.BGP >> (2 * color)) & 0b11;
-        match color {
-            0 => Color::WHITE,
-            1 => Color::RGB(0xaa, 0xaa, 0xaa),
-            2 => Color::RGB(0x55, 0x55, 0x55),
-            3 => Color::BLACK,
-            _ => panic!("Incorrect palette color: {}", color),
-        }
+        self.dmg_palette[color as usize]
+    }
+
+    fn decode_obj_palette(&self, color: u8, obp1: bool) -> Color {
+        let palette = if obp1 {
+            self.registers.OBP1
+        } else {
+            self.registers.OBP0
+        };
+        let color = (palette >> (2 * color)) & 0b11;
+        self.dmg_palette[color as usize]
+    }
+
+    /// Look up `color` (0-3) in one of the eight CGB palettes held in the
+    /// given 64-byte palette RAM and convert the little-endian RGB555 entry
+    /// to an 8-bit-per-channel `Color`.
+    fn decode_cgb_palette(&self, palette_ram: &[u8; 64], palette: u8, color: u8) -> Color {
+        let i = (palette as usize * 4 + color as usize) * 2;
+        let rgb555 = palette_ram[i] as u16 | (palette_ram[i + 1] as u16) << 8;
+        let scale = |c: u16| ((c * 0xFF + 15) / 31) as u8;
+        Color::RGB(
+            scale(rgb555 & 0x1F),
+            scale((rgb555 >> 5) & 0x1F),
+            scale((rgb555 >> 10) & 0x1F),
+        )
     }
 }